@@ -1,6 +1,9 @@
 use chrono::prelude::*;
+use clap::Parser;
 use lazy_static::*;
 use regex::Regex;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::io::prelude::*;
@@ -8,59 +11,109 @@ use std::io::BufReader;
 use std::sync::mpsc;
 use std::thread;
 
-#[derive(Debug)]
+// The set of TODO-state keywords that count as "active" vs. "done",
+// mirroring org-mode's `#+TODO: active-kws | done-kws` file header.
+#[derive(Debug, Clone)]
+struct TodoKeywords {
+    active: Vec<String>,
+    done: Vec<String>,
+}
+
+impl Default for TodoKeywords {
+    fn default() -> Self {
+        return TodoKeywords {
+            active: vec!["TODO", "NEXT", "STARTED", "PROJECT"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            done: vec!["DONE", "NVM"].iter().map(|s| s.to_string()).collect(),
+        };
+    }
+}
+
+// Parse an org `#+TODO: TODO(t) NEXT | DONE(d) NVM` header line. Fast-select
+// hints like `(t)` are dropped; only the bare keyword is kept.
+fn parse_todo_keywords(line: &str) -> Option<TodoKeywords> {
+    let rest = line.trim_start().strip_prefix("#+TODO:")?;
+    let mut halves = rest.splitn(2, '|');
+    let active_part = halves.next().unwrap_or("");
+    let done_part = halves.next().unwrap_or("");
+
+    let clean = |s: &str| -> Vec<String> {
+        s.split_whitespace()
+            .map(|w| w.split('(').next().unwrap().to_string())
+            .filter(|w| !w.is_empty())
+            .collect()
+    };
+
+    let active = clean(active_part);
+    let done = clean(done_part);
+    if active.is_empty() && done.is_empty() {
+        return None;
+    }
+    return Some(TodoKeywords {
+        active: active,
+        done: done,
+    });
+}
+
+#[derive(Debug, Serialize)]
 struct Heading {
     title: String,
     level: usize,
     state: String,
+    priority: Option<char>,
     tags: Vec<String>,
     scheduled: Option<TimeRange>,
     deadline: Option<TimeRange>,
+    closed: Option<TimeRange>,
     logged: Vec<TimeRange>,
     logged_active: Option<NaiveDateTime>,
     timestamps: Vec<TimeRange>,
+    #[serde(skip)]
+    keywords: TodoKeywords,
 }
 
 impl Heading {
     fn is_action(&self) -> bool {
-        return self.state == "TODO"
-            || self.state == "NEXT"
-            || self.state == "STARTED"
-            || self.state == "PROJECT";
+        return self.keywords.active.iter().any(|k| k == &self.state);
     }
 
-    // fn is_done(&self) -> bool {
-    //     return self.state == "DONE" ||
-    //         self.state == "NVM";
-    // }
+    fn is_done(&self) -> bool {
+        return self.keywords.done.iter().any(|k| k == &self.state);
+    }
 
-    fn is_clocked_now(&self) -> bool {
+    // `now` is threaded through as a parameter rather than read from
+    // Local::now() internally, so callers can ask "was this action/event
+    // active as of some other reference instant" (e.g. a --from/--to
+    // query window) instead of only the real current time.
+    fn is_clocked_now(&self, now: NaiveDateTime) -> bool {
         if self.logged_active.is_none() {
             return false;
         }
 
-        let now = Local::now().naive_local();
         return self.logged_active.unwrap() < now;
     }
 
-    fn is_action_now(&self) -> bool {
+    fn is_action_now(&self, now: NaiveDateTime) -> bool {
         if !self.is_action() {
             return false;
         }
 
-        let now = Local::now().naive_local();
+        // is_during() consults occurrence_covering(), so a repeating
+        // SCHEDULED timestamp shows as "now" on every cycle, not just
+        // the first.
         return match &self.scheduled {
             Some(s) => s.is_during(now),
             None => false,
         };
     }
 
-    fn is_event_now(&self) -> bool {
+    fn is_event_now(&self, now: NaiveDateTime) -> bool {
         if self.is_action() {
             return false;
         }
 
-        let now = Local::now().naive_local();
         return match &self.scheduled {
             Some(s) => s.is_during(now),
             None => false,
@@ -68,12 +121,11 @@ impl Heading {
         // loop over timestamps here!
     }
 
-    fn is_overdue_now(&self) -> bool {
+    fn is_overdue_now(&self, now: NaiveDateTime) -> bool {
         if !self.is_action() {
             return false;
         }
 
-        let now = Local::now().naive_local();
         return match &self.scheduled {
             Some(s) => s.is_before(now),
             None => match &self.deadline {
@@ -84,15 +136,25 @@ impl Heading {
     }
 
     fn print_action(&self) -> String {
-        return format!("[ ] {}", &self.title);
+        return match self.priority {
+            Some(p) => format!("^fg({})[#{}]^fg() [ ] {}", priority_color(p), p, &self.title),
+            None => format!("[ ] {}", &self.title),
+        };
     }
 
     fn print_overdue(&self) -> String {
-        return format!("^fg(orangered)[!]^fg() {}", &self.title);
+        return match self.priority {
+            Some(p) => format!(
+                "^fg({})[#{}]^fg() ^fg(orangered)[!]^fg() {}",
+                priority_color(p),
+                p,
+                &self.title
+            ),
+            None => format!("^fg(orangered)[!]^fg() {}", &self.title),
+        };
     }
 
-    fn print_clocked(&self) -> String {
-        let now = Local::now().naive_local();
+    fn print_clocked(&self, now: NaiveDateTime) -> String {
         let delta = now - self.logged_active.unwrap();
         return format!(
             "^fg(orange)[{:02}:{:02}:{:02}] {}^fg()",
@@ -107,41 +169,187 @@ impl Heading {
         return format!("[ ] {}", &self.title);
     }
 
-    fn most_recently_started(&self, now: NaiveDateTime) -> Option<&TimeRange> {
-        if self.scheduled.is_some() {
-            return self.scheduled.as_ref();
+    fn most_recently_started(&self, now: NaiveDateTime) -> Option<TimeRange> {
+        if let Some(s) = &self.scheduled {
+            let (start, end) = s.occurrence_covering(now);
+            return Some(TimeRange {
+                start: start,
+                end: end,
+                repeater: s.repeater,
+            });
         }
 
         let mut recent_dur = chrono::Duration::max_value();
-        let mut recent_range: Option<&TimeRange> = None;
+        let mut recent_range: Option<TimeRange> = None;
         for tr in &self.timestamps {
-            if now < tr.start {
+            let (start, end) = tr.occurrence_covering(now);
+            if now < start {
                 continue;
             }
 
-            let start_dur = now - tr.start;
+            let start_dur = now - start;
             if start_dur < recent_dur {
                 recent_dur = start_dur;
-                recent_range = Some(tr);
+                recent_range = Some(TimeRange {
+                    start: start,
+                    end: end,
+                    repeater: tr.repeater,
+                });
             }
         }
         return recent_range;
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+enum RepeaterKind {
+    // `+n`: step forward n units at a time; occurrence_covering lands on
+    // the latest occurrence that has already started as of `ts`.
+    Cumulate,
+    // `++n`: same landing spot as Cumulate, but intended for reminders
+    // that should "catch up" rather than pile up missed occurrences.
+    CatchUp,
+    // `.+n`: org anchors this off whenever the item was last completed
+    // rather than the original start. occurrence_covering projects it
+    // the same way as Cumulate/CatchUp (stepping forward from the
+    // original start until a step would overshoot `ts`), since that's
+    // the only anchor this struct tracks.
+    Restart,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+enum RepeaterUnit {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+struct Repeater {
+    kind: RepeaterKind,
+    n: i64,
+    unit: RepeaterUnit,
+}
+
+impl Repeater {
+    fn advance(&self, dt: NaiveDateTime) -> NaiveDateTime {
+        match self.unit {
+            RepeaterUnit::Hour => dt + chrono::Duration::hours(self.n),
+            RepeaterUnit::Day => dt + chrono::Duration::days(self.n),
+            RepeaterUnit::Week => dt + chrono::Duration::weeks(self.n),
+            RepeaterUnit::Month => {
+                let months = chrono::Months::new(self.n as u32);
+                dt.date().checked_add_months(months).unwrap().and_time(dt.time())
+            }
+            RepeaterUnit::Year => {
+                let months = chrono::Months::new((self.n * 12) as u32);
+                dt.date().checked_add_months(months).unwrap().and_time(dt.time())
+            }
+        }
+    }
+}
+
+fn parse_repeater(tok: &str) -> Option<Repeater> {
+    lazy_static! {
+        static ref REPEATER_RE: Regex = Regex::new(r"^(\+\+|\.\+|\+)(\d+)([hdwmy])$").unwrap();
+    }
+    let caps = REPEATER_RE.captures(tok)?;
+    let kind = match &caps[1] {
+        "++" => RepeaterKind::CatchUp,
+        ".+" => RepeaterKind::Restart,
+        _ => RepeaterKind::Cumulate,
+    };
+    let n: i64 = caps[2].parse().ok()?;
+    if n == 0 {
+        // A zero-length interval would never advance past `ts`, hanging
+        // occurrence_covering's projection loop forever.
+        return None;
+    }
+    let unit = match &caps[3] {
+        "h" => RepeaterUnit::Hour,
+        "d" => RepeaterUnit::Day,
+        "w" => RepeaterUnit::Week,
+        "m" => RepeaterUnit::Month,
+        "y" => RepeaterUnit::Year,
+        _ => return None,
+    };
+    Some(Repeater { kind, n, unit })
+}
+
+// Parse and strip a leading priority cookie like `[#A] ` (A/B/C by org
+// convention, though any single letter or digit is accepted here).
+fn parse_priority(s: &str) -> (Option<char>, String) {
+    lazy_static! {
+        static ref PRIORITY_RE: Regex = Regex::new(r"^\[#([A-Za-z0-9])\]\s*").unwrap();
+    }
+    match PRIORITY_RE.captures(s) {
+        Some(caps) => {
+            let p = caps[1].chars().next().unwrap().to_ascii_uppercase();
+            let rest = s[caps.get(0).unwrap().end()..].to_string();
+            return (Some(p), rest);
+        }
+        None => {
+            return (None, s.to_string());
+        }
+    }
+}
+
+fn priority_color(p: char) -> &'static str {
+    return match p {
+        'A' => "red",
+        'B' => "yellow",
+        'C' => "green",
+        _ => "white",
+    };
+}
+
+#[derive(Debug, Clone, Serialize)]
 struct TimeRange {
     start: NaiveDateTime,
     end: NaiveDateTime,
+    repeater: Option<Repeater>,
 }
 
 impl TimeRange {
+    // Project start/end onto whichever occurrence of a repeating range is
+    // current as of `ts`: the most recent occurrence that has already
+    // started, or the first occurrence if none has started yet. Ranges
+    // with no repeater are returned unchanged.
+    fn occurrence_covering(&self, ts: NaiveDateTime) -> (NaiveDateTime, NaiveDateTime) {
+        let repeater = match &self.repeater {
+            Some(r) => r,
+            None => return (self.start, self.end),
+        };
+
+        let span = self.end - self.start;
+        match repeater.kind {
+            RepeaterKind::Cumulate | RepeaterKind::CatchUp | RepeaterKind::Restart => {
+                if ts <= self.start {
+                    return (self.start, self.end);
+                }
+                let mut start = self.start;
+                loop {
+                    let next_start = repeater.advance(start);
+                    if next_start > ts {
+                        break;
+                    }
+                    start = next_start;
+                }
+                (start, start + span)
+            }
+        }
+    }
+
     fn is_during(&self, ts: NaiveDateTime) -> bool {
-        return self.start < ts && ts < self.end;
+        let (start, end) = self.occurrence_covering(ts);
+        return start < ts && ts < end;
     }
 
     fn is_before(&self, ts: NaiveDateTime) -> bool {
-        return self.start < ts && self.end < ts;
+        let (start, end) = self.occurrence_covering(ts);
+        return start < ts && end < ts;
     }
 
     // fn is_after(&self, ts: NaiveDateTime) -> bool{
@@ -149,21 +357,30 @@ impl TimeRange {
     // }
 }
 
-fn most_recent<'a>(hs: &Vec<&'a Heading>) -> Option<&'a Heading> {
+// Lower rank means higher priority ('A' outranks 'B'), and any explicit
+// priority outranks a heading with none.
+fn priority_rank(p: Option<char>) -> u8 {
+    return match p {
+        Some(c) => c as u8,
+        None => u8::MAX,
+    };
+}
+
+fn most_recent<'a>(hs: &Vec<&'a Heading>, now: NaiveDateTime) -> Option<&'a Heading> {
     if hs.len() == 0 {
         return None;
     }
 
-    let now = Local::now().naive_local();
-
-    let mut lowest = Vec::<(&TimeRange, &Heading)>::new();
+    let mut lowest = Vec::<(TimeRange, &Heading)>::new();
     for h in hs {
-        lowest.push((h.most_recently_started(now).unwrap().to_owned(), h));
+        lowest.push((h.most_recently_started(now).unwrap(), h));
     }
 
-    let mut lowest_seen: (&TimeRange, &Heading) = lowest[0];
+    let mut lowest_seen = lowest.remove(0);
     for pair in lowest {
-        if pair.0.start < lowest_seen.0.start {
+        let pair_rank = priority_rank(pair.1.priority);
+        let seen_rank = priority_rank(lowest_seen.1.priority);
+        if pair_rank < seen_rank || (pair_rank == seen_rank && pair.0.start < lowest_seen.0.start) {
             lowest_seen = pair;
         }
     }
@@ -185,20 +402,22 @@ fn next_timerange(buf: &str) -> Option<TimeRange> {
     let ts_start: &[_] = &['[', '<'];
     let ts_end: &[_] = &['>', ']'];
 
-    match buf.find(ts_start) {
-        Some(s) => match buf[s..].find(ts_end) {
-            Some(e) => {
-                let res = parse_timerange(&buf[s..s + e + 1].to_owned());
-                return res;
-            }
-            None => {
-                return None;
+    let s = buf.find(ts_start)?;
+    let e = buf[s..].find(ts_end)?;
+    let mut end = s + e + 1;
+
+    // A ranged entry like [a]--[b] is two bracketed timestamps joined by
+    // "--"; include the second bracket too, or parse_timerange only ever
+    // sees the first and the range comes out zero-duration.
+    if buf[end..].starts_with("--") {
+        if let Some(s2) = buf[end + 2..].find(ts_start) {
+            if let Some(e2) = buf[end + 2 + s2..].find(ts_end) {
+                end = end + 2 + s2 + e2 + 1;
             }
-        },
-        None => {
-            return None;
         }
     }
+
+    return parse_timerange(&buf[s..end].to_owned());
 }
 
 // Parse a date and time that may look like:
@@ -207,44 +426,62 @@ fn parse_timerange(buf: &str) -> Option<TimeRange> {
     // is this a double date
     match buf.find("--") {
         Some(beg) => {
-            let (start, _) = parse_date_str(&buf[0..beg]);
-            let (end, _) = parse_date_str(&buf[beg + 2..]);
+            let (start, _, repeater1) = parse_date_str(&buf[0..beg]);
+            let (end, _, repeater2) = parse_date_str(&buf[beg + 2..]);
             return Some(TimeRange {
                 start: start,
                 end: end,
+                repeater: repeater2.or(repeater1),
             });
         }
         None => {
-            let (start, end) = parse_date_str(&buf);
+            let (start, end, repeater) = parse_date_str(&buf);
             return Some(TimeRange {
                 start: start,
                 end: end,
+                repeater: repeater,
             });
         }
     }
 }
 
 // Parse a time, with a time range potentially
-// <YYYY-MM-DD [dow [time[-endtime]]][ repeat deadline]>
-fn parse_date_str(datestr: &str) -> (NaiveDateTime, NaiveDateTime) {
+// <YYYY-MM-DD [dow [time[-endtime]]][ repeat/cooldown cookie]>
+// The repeat/cooldown cookie (+1w, ++2d, .+1m, ...) can appear either
+// before or after the time-of-day token, so every trailing token is
+// checked against both shapes instead of assuming a fixed position.
+fn parse_date_str(datestr: &str) -> (NaiveDateTime, NaiveDateTime, Option<Repeater>) {
     let trimmable: &[_] = &['[', '<', '>', ']'];
     let base_str = datestr.trim_matches(trimmable);
 
     // split string into it's spaced parts
     let base_splits: Vec<&str> = base_str.split(' ').collect();
 
-    // if there is no time specified
-    if base_splits.len() < 3
-        || base_splits[2].chars().nth(0).unwrap() == '+'
-        || base_splits[2].chars().nth(0).unwrap() == '-'
-        || base_splits[2].chars().nth(0).unwrap() == '.'
-    {
-        let start_date = NaiveDate::parse_from_str(base_splits[0], "%Y-%m-%d").unwrap();
-        let with_time = start_date.and_hms(0, 0, 0);
-        return (with_time, with_time + chrono::Duration::days(1));
+    let mut time_token: Option<&str> = None;
+    let mut repeater: Option<Repeater> = None;
+    for tok in base_splits.iter().skip(2) {
+        match parse_repeater(tok) {
+            Some(r) => repeater = Some(r),
+            None => {
+                if time_token.is_none() {
+                    time_token = Some(tok);
+                }
+            }
+        }
     }
 
-    let time_split: Vec<&str> = base_splits[2].split('-').collect();
+    let start_date = NaiveDate::parse_from_str(base_splits[0], "%Y-%m-%d").unwrap();
+
+    // if there is no time specified
+    let time_token = match time_token {
+        Some(t) => t,
+        None => {
+            let with_time = start_date.and_hms(0, 0, 0);
+            return (with_time, with_time + chrono::Duration::days(1), repeater);
+        }
+    };
+
+    let time_split: Vec<&str> = time_token.split('-').collect();
 
     // if there is no timerange specified
     if time_split.len() == 1 {
@@ -254,7 +491,7 @@ fn parse_date_str(datestr: &str) -> (NaiveDateTime, NaiveDateTime) {
             "%Y-%m-%d %H:%M",
         )
         .unwrap();
-        return (start_date, start_date);
+        return (start_date, start_date, repeater);
     }
 
     // now we now we have YYYY-MM-DD Dow HH:MM-HH:MM
@@ -265,10 +502,10 @@ fn parse_date_str(datestr: &str) -> (NaiveDateTime, NaiveDateTime) {
         NaiveDateTime::parse_from_str(&[base_splits[0], time_split[1]].join(" "), "%Y-%m-%d %H:%M")
             .unwrap();
 
-    return (start_date, end_date);
+    return (start_date, end_date, repeater);
 }
 
-fn parse_single_org_entry(entry: Vec<String>) -> Option<Heading> {
+fn parse_single_org_entry(entry: Vec<String>, keywords: &TodoKeywords) -> Option<Heading> {
     if entry.len() == 0 {
         return None;
     }
@@ -287,15 +524,17 @@ fn parse_single_org_entry(entry: Vec<String>) -> Option<Heading> {
 
     firstline = firstline[level + 1..].to_string();
 
-    let state = if firstline.starts_with("TODO ") {
-        firstline = firstline[5..].to_string();
-        "TODO"
-    } else if firstline.starts_with("DONE ") {
-        firstline = firstline[5..].to_string();
-        "DONE"
-    } else {
-        ""
-    };
+    let mut state = "";
+    for kw in keywords.active.iter().chain(keywords.done.iter()) {
+        let prefix = format!("{} ", kw);
+        if firstline.starts_with(&prefix) {
+            firstline = firstline[prefix.len()..].to_string();
+            state = kw.as_str();
+            break;
+        }
+    }
+
+    let (priority, firstline) = parse_priority(&firstline);
 
     // parse org tags
     let mut tags = Vec::new();
@@ -313,32 +552,36 @@ fn parse_single_org_entry(entry: Vec<String>) -> Option<Heading> {
 
     line += 1;
 
-    // check if second line has SCHEDULED/DEADLINE
+    // check if second line has CLOSED/SCHEDULED/DEADLINE
     if entry.len() < 2 {
         return Some(Heading {
             title: firstline,
             level: level,
             state: state.to_string(),
+            priority: priority,
             tags: tags,
             deadline: None,
             scheduled: None,
+            closed: None,
             logged: Vec::<TimeRange>::new(),
             logged_active: None,
             timestamps: Vec::<TimeRange>::new(),
+            keywords: keywords.clone(),
         });
     }
 
     let secondline = &entry[line];
     let scheduled = next_prefix_timerange(&secondline, "SCHEDULED: ");
     let deadline = next_prefix_timerange(&secondline, "DEADLINE: ");
-    // skip scheduled/deadline line if there
-    if scheduled.is_some() || deadline.is_some() {
+    let closed = next_prefix_timerange(&secondline, "CLOSED: ");
+    // skip the planning line if there
+    if scheduled.is_some() || deadline.is_some() || closed.is_some() {
         line += 1;
     }
 
     // parse over properties
 
-    if entry[line] == ":PROPERTIES:" {
+    if entry.len() > line && entry[line] == ":PROPERTIES:" {
         line += 1;
         while entry[line] != ":END:" {
             line += 1;
@@ -365,7 +608,7 @@ fn parse_single_org_entry(entry: Vec<String>) -> Option<Heading> {
                         }
                     } else {
                         // active clock!
-                        let (res_time, _) = parse_date_str(&entry[line][7..]);
+                        let (res_time, _, _) = parse_date_str(&entry[line][7..]);
                         logged_active = Some(res_time);
                     }
                 }
@@ -395,17 +638,43 @@ fn parse_single_org_entry(entry: Vec<String>) -> Option<Heading> {
         title: firstline,
         level: level,
         state: state.to_string(),
+        priority: priority,
         tags: tags,
         deadline: deadline,
         scheduled: scheduled,
+        closed: closed,
         logged: logged,
         logged_active: logged_active,
         timestamps: timestamps,
+        keywords: keywords.clone(),
     });
 }
 
+// Scan a file for a `#+TODO:` header line before parsing its headings, so
+// a file can declare its own active/done keyword set. Falls back to the
+// default TODO/NEXT/STARTED/PROJECT | DONE/NVM split if none is found.
+fn scan_todo_keywords(filename: &str) -> TodoKeywords {
+    let file = match fs::File::open(&filename) {
+        Ok(f) => f,
+        Err(_) => return TodoKeywords::default(),
+    };
+    let br = BufReader::new(file);
+    for line in br.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => break,
+        };
+        if let Some(kw) = parse_todo_keywords(&line) {
+            return kw;
+        }
+    }
+    return TodoKeywords::default();
+}
+
 // read a file, creating entry arrays.
 fn parse_org_dates(filename: &str, result_send: mpsc::Sender<Heading>) {
+    let keywords = scan_todo_keywords(filename);
+
     // read file, and send line by line?
     let file = match fs::File::open(&filename) {
         Ok(f) => f,
@@ -421,7 +690,7 @@ fn parse_org_dates(filename: &str, result_send: mpsc::Sender<Heading>) {
     for line in br.lines().map(|l| l.unwrap()) {
         if line.len() > 0 && line.starts_with("*") {
             if rest.len() > 0 {
-                match parse_single_org_entry(rest) {
+                match parse_single_org_entry(rest, &keywords) {
                     Some(heading) => result_send.send(heading).unwrap(),
                     None => (),
                 }
@@ -431,13 +700,136 @@ fn parse_org_dates(filename: &str, result_send: mpsc::Sender<Heading>) {
         rest.push(line);
     }
     if rest.len() > 0 {
-        match parse_single_org_entry(rest) {
+        match parse_single_org_entry(rest, &keywords) {
             Some(heading) => result_send.send(heading).unwrap(),
             None => (),
         }
     }
 }
 
+// The clocked duration of [start, end) that overlaps [from, to], where
+// either bound being None means "unbounded" on that side.
+fn clip_duration(
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+) -> chrono::Duration {
+    let clipped_start = match from {
+        Some(f) => std::cmp::max(start, f),
+        None => start,
+    };
+    let clipped_end = match to {
+        Some(t) => std::cmp::min(end, t),
+        None => end,
+    };
+    if clipped_end <= clipped_start {
+        return chrono::Duration::zero();
+    }
+    return clipped_end - clipped_start;
+}
+
+fn add_duration(map: &mut BTreeMap<String, chrono::Duration>, key: String, dur: chrono::Duration) {
+    map.entry(key).and_modify(|d| *d = *d + dur).or_insert(dur);
+}
+
+fn format_duration(d: chrono::Duration) -> String {
+    let total_minutes = d.num_minutes();
+    return format!("{:02}:{:02}", total_minutes / 60, total_minutes % 60);
+}
+
+struct ClockReport {
+    by_day: BTreeMap<String, chrono::Duration>,
+    by_week: BTreeMap<String, chrono::Duration>,
+    by_tag: BTreeMap<String, chrono::Duration>,
+    by_heading: Vec<(String, chrono::Duration)>,
+}
+
+// Aggregate every heading's LOGBOOK clock entries, plus its currently
+// running clock (if any) up through `now`, scoped to [from, to].
+fn build_clock_report(
+    headings: &[Heading],
+    now: NaiveDateTime,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+) -> ClockReport {
+    let mut by_day = BTreeMap::<String, chrono::Duration>::new();
+    let mut by_week = BTreeMap::<String, chrono::Duration>::new();
+    let mut by_tag = BTreeMap::<String, chrono::Duration>::new();
+    let mut by_heading = Vec::<(String, chrono::Duration)>::new();
+
+    for h in headings {
+        let mut entries: Vec<(NaiveDateTime, NaiveDateTime)> =
+            h.logged.iter().map(|tr| (tr.start, tr.end)).collect();
+        if let Some(active) = h.logged_active {
+            entries.push((active, now));
+        }
+
+        let mut heading_total = chrono::Duration::zero();
+        for (start, end) in entries {
+            let dur = clip_duration(start, end, from, to);
+            if dur <= chrono::Duration::zero() {
+                continue;
+            }
+            heading_total = heading_total + dur;
+
+            let day_key = start.date().format("%Y-%m-%d").to_string();
+            add_duration(&mut by_day, day_key, dur);
+
+            let iso = start.date().iso_week();
+            let week_key = format!("{}-W{:02}", iso.year(), iso.week());
+            add_duration(&mut by_week, week_key, dur);
+
+            if h.tags.is_empty() {
+                add_duration(&mut by_tag, "untagged".to_string(), dur);
+            } else {
+                for tag in &h.tags {
+                    add_duration(&mut by_tag, tag.clone(), dur);
+                }
+            }
+        }
+
+        if heading_total > chrono::Duration::zero() {
+            by_heading.push((h.title.clone(), heading_total));
+        }
+    }
+
+    return ClockReport {
+        by_day: by_day,
+        by_week: by_week,
+        by_tag: by_tag,
+        by_heading: by_heading,
+    };
+}
+
+fn render_clock_report(report: &ClockReport) -> String {
+    let mut out = String::new();
+
+    out.push_str("Totals by day:\n");
+    for (day, dur) in &report.by_day {
+        out.push_str(&format!("  {}  {}\n", day, format_duration(*dur)));
+    }
+
+    out.push_str("Totals by week:\n");
+    for (week, dur) in &report.by_week {
+        out.push_str(&format!("  {}  {}\n", week, format_duration(*dur)));
+    }
+
+    out.push_str("Totals by tag:\n");
+    for (tag, dur) in &report.by_tag {
+        out.push_str(&format!("  {:<20}  {}\n", tag, format_duration(*dur)));
+    }
+
+    out.push_str("By heading:\n");
+    let mut by_heading = report.by_heading.clone();
+    by_heading.sort_by(|a, b| b.1.cmp(&a.1));
+    for (title, dur) in &by_heading {
+        out.push_str(&format!("  {}  {}\n", format_duration(*dur), title));
+    }
+
+    return out;
+}
+
 fn launch_fns(orgfiles: Vec<String>, result_send: mpsc::Sender<Heading>) {
     for of in orgfiles {
         let thread_sender = result_send.clone();
@@ -447,14 +839,253 @@ fn launch_fns(orgfiles: Vec<String>, result_send: mpsc::Sender<Heading>) {
     }
 }
 
-fn main() {
-    let (result_send, result_recv) = mpsc::channel();
+// `Public` calendars are meant to be shared: titles are hidden unless the
+// heading opts in with the `:joinme:` disclosure tag. `Private`
+// calendars keep the real titles, for a user's own eyes only.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CalendarPrivacy {
+    Public,
+    Private,
+}
+
+// Org tags that carry a semantic meaning for a shared availability
+// calendar, distinct from an opaque "busy" block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BlockKind {
+    Busy,
+    Tentative,
+    Rough,
+    JoinMe,
+    SelfBlock,
+}
+
+impl BlockKind {
+    fn from_tags(tags: &[String]) -> Option<BlockKind> {
+        for t in tags {
+            match t.as_str() {
+                "busy" => return Some(BlockKind::Busy),
+                "tentative" => return Some(BlockKind::Tentative),
+                "rough" => return Some(BlockKind::Rough),
+                "joinme" => return Some(BlockKind::JoinMe),
+                "self" => return Some(BlockKind::SelfBlock),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn css_class(&self) -> &'static str {
+        match self {
+            BlockKind::Busy => "busy",
+            BlockKind::Tentative => "tentative",
+            BlockKind::Rough => "rough",
+            BlockKind::JoinMe => "joinme",
+            BlockKind::SelfBlock => "self",
+        }
+    }
+
+    fn legend_label(&self) -> &'static str {
+        match self {
+            BlockKind::Busy => "Busy",
+            BlockKind::Tentative => "Tentative (timing not final)",
+            BlockKind::Rough => "Rough (fuzzy start/end)",
+            BlockKind::JoinMe => "Open invitation",
+            BlockKind::SelfBlock => "Focus block (reschedulable)",
+        }
+    }
+}
+
+struct CalendarEntry<'a> {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    heading: &'a Heading,
+}
+
+// Walk every scheduled/timestamp range on every heading that is not in a
+// done state and, for each day
+// in `window`, ask occurrence_covering() whether that range lands on that
+// day. This is what lets a repeating SCHEDULED timestamp show up on every
+// day it recurs within the window, not just its first occurrence.
+fn collect_calendar_entries<'a>(headings: &'a [Heading], window: &[NaiveDate]) -> Vec<CalendarEntry<'a>> {
+    let mut entries = Vec::new();
+    for h in headings {
+        if h.is_done() {
+            continue;
+        }
+
+        let mut ranges: Vec<&TimeRange> = Vec::new();
+        if let Some(s) = &h.scheduled {
+            ranges.push(s);
+        }
+        for t in &h.timestamps {
+            ranges.push(t);
+        }
+
+        for tr in ranges {
+            for day in window {
+                let probe = day.and_hms(23, 59, 59);
+                let (start, end) = tr.occurrence_covering(probe);
+                if start.date() == *day {
+                    entries.push(CalendarEntry {
+                        start: start,
+                        end: end,
+                        heading: h,
+                    });
+                }
+            }
+        }
+    }
+    return entries;
+}
+
+fn html_escape(s: &str) -> String {
+    return s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+}
+
+const CALENDAR_CSS: &str = "
+body { font-family: sans-serif; background: #1e1e1e; color: #ddd; }
+.calendar { display: flex; flex-wrap: wrap; gap: 8px; }
+.day { width: 13%; min-width: 140px; border: 1px solid #444; border-radius: 4px; padding: 4px; }
+.day-header { font-weight: bold; border-bottom: 1px solid #444; margin-bottom: 4px; }
+.event { border-radius: 3px; padding: 2px 4px; margin-bottom: 2px; font-size: 0.9em; }
+.event .time { opacity: 0.7; margin-right: 4px; }
+.busy { background: #7a2e2e; }
+.tentative { background: #7a6a2e; }
+.rough { background: #4a4a4a; border: 1px dashed #888; }
+.joinme { background: #2e6a7a; }
+.self { background: #3a5a3a; }
+.legend { margin-top: 12px; }
+.legend-item { display: inline-block; border-radius: 3px; padding: 2px 8px; margin-right: 8px; }
+";
+
+// Render a 14-day HTML calendar grid of every heading not in a done state
+// with a scheduled time or timestamp, starting at `start_day`. In `Public` mode, heading
+// titles are hidden behind a generic "Busy" label unless the heading is
+// tagged `:joinme:`, the only tag whose semantics invite disclosure; all
+// other recognized tags (see `BlockKind`) only affect styling/legend.
+fn render_html_calendar(headings: &[Heading], privacy: CalendarPrivacy, start_day: NaiveDate) -> String {
+    let window: Vec<NaiveDate> = (0..14).map(|i| start_day + chrono::Duration::days(i)).collect();
+    let entries = collect_calendar_entries(headings, &window);
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>focus-org calendar</title>\n<style>");
+    html.push_str(CALENDAR_CSS);
+    html.push_str("</style>\n</head>\n<body>\n<div class=\"calendar\">\n");
+
+    for day in &window {
+        html.push_str(&format!(
+            "  <div class=\"day\">\n    <div class=\"day-header\">{}</div>\n",
+            day.format("%a %Y-%m-%d")
+        ));
+
+        let mut day_entries: Vec<&CalendarEntry> =
+            entries.iter().filter(|e| e.start.date() == *day).collect();
+        day_entries.sort_by_key(|e| e.start);
+
+        for entry in day_entries {
+            let kind = BlockKind::from_tags(&entry.heading.tags);
+            let class = kind.map(|k| k.css_class()).unwrap_or("busy");
+            let title = match (privacy, kind) {
+                (CalendarPrivacy::Private, _) => entry.heading.title.clone(),
+                (CalendarPrivacy::Public, Some(BlockKind::JoinMe)) => entry.heading.title.clone(),
+                (CalendarPrivacy::Public, _) => "Busy".to_string(),
+            };
+            html.push_str(&format!(
+                "    <div class=\"event {}\"><span class=\"time\">{}-{}</span><span class=\"title\">{}</span></div>\n",
+                class,
+                entry.start.format("%H:%M"),
+                entry.end.format("%H:%M"),
+                html_escape(&title),
+            ));
+        }
+
+        html.push_str("  </div>\n");
+    }
+    html.push_str("</div>\n<div class=\"legend\">\n");
+
+    for kind in &[
+        BlockKind::Busy,
+        BlockKind::Tentative,
+        BlockKind::Rough,
+        BlockKind::JoinMe,
+        BlockKind::SelfBlock,
+    ] {
+        html.push_str(&format!(
+            "  <span class=\"legend-item {}\">{}</span>\n",
+            kind.css_class(),
+            kind.legend_label()
+        ));
+    }
+
+    html.push_str("</div>\n</body>\n</html>\n");
+    return html;
+}
+
+/// Summarize org-mode headings for a status line, report, or calendar export.
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Cli {
+    /// Org directory to scan (repeatable). Defaults to ~/org/.
+    #[arg(long = "dir")]
+    dirs: Vec<String>,
+
+    /// Only consider headings carrying this tag (repeatable, any match).
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+
+    /// Only consider headings in this TODO/DONE state (repeatable, any match).
+    #[arg(long = "state")]
+    states: Vec<String>,
+
+    /// Reference instant for "now" (YYYY-MM-DD or "YYYY-MM-DD HH:MM").
+    /// Defaults to the real current time; also scopes --report totals
+    /// together with --to.
+    #[arg(long)]
+    from: Option<String>,
+
+    /// End of the time window started by --from.
+    #[arg(long)]
+    to: Option<String>,
+
+    /// Output format for the status line: dzen, plain, or json.
+    #[arg(long, default_value = "dzen")]
+    format: String,
+
+    /// Emit a 14-day HTML calendar instead of a status line.
+    #[arg(long)]
+    html: bool,
+
+    /// Keep real heading titles in --html output. Without it, titles are
+    /// replaced by "Busy" except for :joinme:-tagged entries.
+    #[arg(long)]
+    private: bool,
+
+    /// Write --html output to a file instead of stdout.
+    #[arg(long = "html-out")]
+    html_out: Option<String>,
+
+    /// Emit a clocktable time-tracking report instead of a status line.
+    #[arg(long)]
+    report: bool,
+}
+
+fn parse_cli_datetime(s: &str) -> NaiveDateTime {
+    if let Ok(dt) = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M") {
+        return dt;
+    }
+    return NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap().and_hms(0, 0, 0);
+}
+
+fn default_org_dirs() -> Vec<String> {
     let home_dir = env::var("HOME").unwrap();
+    return vec![home_dir + "/org"];
+}
 
-    // As far as I can tell, this reads a directory and then returns a
-    // list of strings of the contents of that directory. Jesus
-    // christ.
-    let orgfiles: Vec<String> = fs::read_dir(home_dir.to_string() + "/org/")
+// As far as I can tell, this reads a directory and then returns a list of
+// strings of the contents of that directory. Jesus christ.
+fn list_org_files(dir: &str) -> Vec<String> {
+    return fs::read_dir(dir)
         .unwrap()
         .filter(|x| !x.as_ref().unwrap().file_type().unwrap().is_dir())
         .filter(|x| {
@@ -465,77 +1096,327 @@ fn main() {
                 .unwrap()
                 .starts_with(".")
         })
-        .map(|x| {
-            home_dir.to_string()
-                + "/org/"
-                + x.unwrap().path().file_name().unwrap().to_str().unwrap()
-        })
+        .map(|x| dir.to_string() + "/" + x.unwrap().path().file_name().unwrap().to_str().unwrap())
         .collect();
+}
 
-    launch_fns(orgfiles, result_send);
+fn matches_tags(h: &Heading, tags: &[String]) -> bool {
+    return tags.is_empty() || tags.iter().any(|t| h.tags.contains(t));
+}
+
+fn matches_states(h: &Heading, states: &[String]) -> bool {
+    return states.is_empty() || states.iter().any(|s| s == &h.state);
+}
+
+struct Classified<'a> {
+    clocked: Vec<&'a Heading>,
+    action: Vec<&'a Heading>,
+    event: Vec<&'a Heading>,
+    overdue: Vec<&'a Heading>,
+}
 
-    let mut all = Vec::<Heading>::new();
+fn classify<'a>(headings: &'a [Heading], now: NaiveDateTime) -> Classified<'a> {
     let mut clocked = Vec::<&Heading>::new();
     let mut action = Vec::<&Heading>::new();
     let mut event = Vec::<&Heading>::new();
     let mut overdue = Vec::<&Heading>::new();
-    for message in result_recv {
-        all.push(message);
-    }
-    for message in &all {
-        if message.is_clocked_now() {
-            clocked.push(&message);
-        } else if message.is_action_now() {
-            action.push(&message);
-        } else if message.is_event_now() {
-            event.push(&message);
-        } else if message.is_overdue_now() {
-            overdue.push(&message);
+
+    for h in headings {
+        if h.is_clocked_now(now) {
+            clocked.push(h);
+        } else if h.is_action_now(now) {
+            action.push(h);
+        } else if h.is_event_now(now) {
+            event.push(h);
+        } else if h.is_overdue_now(now) {
+            overdue.push(h);
         }
     }
 
-    print!("^tw()");
+    return Classified {
+        clocked: clocked,
+        action: action,
+        event: event,
+        overdue: overdue,
+    };
+}
+
+fn render_dzen(c: &Classified, now: NaiveDateTime) -> String {
+    let mut out = String::new();
+    out.push_str("^tw()");
 
     let mut actionstr = String::new();
-    if clocked.len() == 1 {
-        actionstr += &clocked[0].print_clocked();
-    } else if action.len() > 0 {
-        match most_recent(&action) {
-            Some(h) => actionstr += &h.print_action(),
-            None => (),
+    if c.clocked.len() == 1 {
+        actionstr += &c.clocked[0].print_clocked(now);
+    } else if c.action.len() > 0 {
+        if let Some(h) = most_recent(&c.action, now) {
+            actionstr += &h.print_action();
         }
-    } else if overdue.len() > 0 {
-        match most_recent(&overdue) {
-            Some(h) => actionstr += &h.print_overdue(),
-            None => (),
+    } else if c.overdue.len() > 0 {
+        if let Some(h) = most_recent(&c.overdue, now) {
+            actionstr += &h.print_overdue();
         }
     }
 
     let mut eventstr = String::new();
-    if event.len() > 0 {
-        match most_recent(&event) {
-            Some(h) => eventstr += &format!("# {}", &h.print_event()),
-            None => (),
+    if c.event.len() > 0 {
+        if let Some(h) = most_recent(&c.event, now) {
+            eventstr += &format!("# {}", &h.print_event());
         }
     }
 
     if actionstr.len() > 0 {
-        print!("{} ", actionstr);
+        out.push_str(&format!("{} ", actionstr));
+    }
+    out.push_str(&eventstr);
+    out.push_str("^cs()\n");
+    return out;
+}
+
+fn priority_prefix(p: Option<char>) -> String {
+    return match p {
+        Some(c) => format!("[#{}] ", c),
+        None => String::new(),
+    };
+}
+
+fn render_plain(c: &Classified, now: NaiveDateTime) -> String {
+    let mut out = String::new();
+    for h in &c.clocked {
+        out.push_str(&format!("clocked\t{}\n", h.print_clocked(now)));
+    }
+    for h in &c.overdue {
+        out.push_str(&format!("overdue\t{}{}\n", priority_prefix(h.priority), h.title));
+    }
+    for h in &c.action {
+        out.push_str(&format!("action\t{}{}\n", priority_prefix(h.priority), h.title));
+    }
+    for h in &c.event {
+        out.push_str(&format!("event\t{}\n", h.title));
+    }
+    return out;
+}
+
+// chrono::Duration has no serde support of its own, so render it as an
+// ISO-8601 duration string (e.g. "PT1H30M0S") for consistency with how
+// NaiveDateTime fields already serialize.
+fn serialize_duration_iso8601<S>(d: &chrono::Duration, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let total_secs = d.num_seconds();
+    let sign = if total_secs < 0 { "-" } else { "" };
+    let secs = total_secs.abs();
+    return s.serialize_str(&format!(
+        "{}PT{}H{}M{}S",
+        sign,
+        secs / 3600,
+        (secs % 3600) / 60,
+        secs % 60
+    ));
+}
+
+#[derive(Serialize)]
+struct ClockedJson<'a> {
+    title: &'a str,
+    #[serde(serialize_with = "serialize_duration_iso8601")]
+    elapsed: chrono::Duration,
+}
+
+#[derive(Serialize)]
+struct ClassifiedJson<'a> {
+    clocked: Vec<ClockedJson<'a>>,
+    action: Vec<&'a Heading>,
+    event: Vec<&'a Heading>,
+    overdue: Vec<&'a Heading>,
+    all: &'a [Heading],
+}
+
+// Emit the classified clocked/action/event/overdue buckets, plus the
+// full parsed list, as structured JSON -- so a waybar/polybar module or
+// editor plugin can do its own rendering instead of parsing dzen markup.
+fn render_json(c: &Classified, all: &[Heading], now: NaiveDateTime) -> String {
+    let clocked: Vec<ClockedJson> = c
+        .clocked
+        .iter()
+        .map(|h| ClockedJson {
+            title: &h.title,
+            elapsed: now - h.logged_active.unwrap(),
+        })
+        .collect();
+
+    let payload = ClassifiedJson {
+        clocked: clocked,
+        action: c.action.clone(),
+        event: c.event.clone(),
+        overdue: c.overdue.clone(),
+        all: all,
+    };
+
+    return serde_json::to_string_pretty(&payload).unwrap() + "\n";
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let (result_send, result_recv) = mpsc::channel();
+    let dirs = if cli.dirs.is_empty() {
+        default_org_dirs()
+    } else {
+        cli.dirs.clone()
+    };
+    let orgfiles: Vec<String> = dirs.iter().flat_map(|d| list_org_files(d)).collect();
+    launch_fns(orgfiles, result_send);
+
+    let from = cli.from.as_ref().map(|s| parse_cli_datetime(s));
+    let to = cli.to.as_ref().map(|s| parse_cli_datetime(s));
+    let now = from.unwrap_or(Local::now().naive_local());
+
+    // No time-window pre-filter here: classify() and build_clock_report()
+    // already project each heading's ranges against `now`/`from`/`to`
+    // themselves (via occurrence_covering/clip_duration), so a blanket
+    // static-overlap filter in front of them can only ever wrongly drop
+    // a heading one of them would have handled correctly -- most notably
+    // a repeating or overdue item once its original anchor has passed.
+    let all: Vec<Heading> = result_recv
+        .into_iter()
+        .filter(|h| matches_tags(h, &cli.tags))
+        .filter(|h| matches_states(h, &cli.states))
+        .collect();
+
+    if cli.html {
+        let privacy = if cli.private {
+            CalendarPrivacy::Private
+        } else {
+            CalendarPrivacy::Public
+        };
+        let html = render_html_calendar(&all, privacy, now.date());
+        match &cli.html_out {
+            Some(path) => fs::write(path, html).unwrap(),
+            None => print!("{}", html),
+        }
+        return;
+    }
+
+    if cli.report {
+        let report = build_clock_report(&all, now, from, to);
+        print!("{}", render_clock_report(&report));
+        return;
+    }
+
+    let classified = classify(&all, now);
+    let out = match cli.format.as_str() {
+        "plain" => render_plain(&classified, now),
+        "json" => render_json(&classified, &all, now),
+        _ => render_dzen(&classified, now),
+    };
+    print!("{}", out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn occurrence_covering_projects_repeater_past_first_occurrence() {
+        let tr = TimeRange {
+            start: NaiveDate::from_ymd(2026, 7, 20).and_hms(9, 0, 0),
+            end: NaiveDate::from_ymd(2026, 7, 20).and_hms(10, 0, 0),
+            repeater: parse_repeater("+1w"),
+        };
+
+        // A week after the first occurrence, is_during should still be
+        // true for the projected occurrence, not stuck on 2026-07-20.
+        let ts = NaiveDate::from_ymd(2026, 7, 27).and_hms(9, 30, 0);
+        assert!(tr.is_during(ts));
+
+        let (start, end) = tr.occurrence_covering(ts);
+        assert_eq!(start, NaiveDate::from_ymd(2026, 7, 27).and_hms(9, 0, 0));
+        assert_eq!(end, NaiveDate::from_ymd(2026, 7, 27).and_hms(10, 0, 0));
     }
-    print!("{}", eventstr);
 
-    println!("^cs()");
+    fn blank_heading(title: &str) -> Heading {
+        return Heading {
+            title: title.to_string(),
+            level: 1,
+            state: "DONE".to_string(),
+            priority: None,
+            tags: Vec::new(),
+            scheduled: None,
+            deadline: None,
+            closed: None,
+            logged: Vec::new(),
+            logged_active: None,
+            timestamps: Vec::new(),
+            keywords: TodoKeywords::default(),
+        };
+    }
+
+    #[test]
+    fn build_clock_report_sums_ranged_clock_entries() {
+        let mut h = blank_heading("Write quarterly report");
+        h.tags = vec!["work".to_string()];
+        h.logged.push(TimeRange {
+            start: NaiveDate::from_ymd(2026, 7, 20).and_hms(9, 0, 0),
+            end: NaiveDate::from_ymd(2026, 7, 20).and_hms(11, 30, 0),
+            repeater: None,
+        });
+
+        let now = NaiveDate::from_ymd(2026, 7, 26).and_hms(12, 0, 0);
+        let report = build_clock_report(&[h], now, None, None);
 
-    for c in clocked {
-        c.print_clocked();
+        assert_eq!(
+            report.by_day.get("2026-07-20"),
+            Some(&chrono::Duration::minutes(150))
+        );
+        assert_eq!(report.by_tag.get("work"), Some(&chrono::Duration::minutes(150)));
+        assert_eq!(
+            report.by_heading,
+            vec![("Write quarterly report".to_string(), chrono::Duration::minutes(150))]
+        );
     }
-    for o in overdue {
-        o.print_overdue();
+
+    #[test]
+    fn parse_todo_keywords_splits_active_and_done() {
+        let kw = parse_todo_keywords("#+TODO: TODO(t) NEXT | DONE(d) NVM").unwrap();
+        assert_eq!(kw.active, vec!["TODO".to_string(), "NEXT".to_string()]);
+        assert_eq!(kw.done, vec!["DONE".to_string(), "NVM".to_string()]);
     }
-    for a in action {
-        a.print_action();
+
+    #[test]
+    fn parse_single_org_entry_parses_closed_and_survives_a_bare_planning_line() {
+        let entry = vec![
+            "* DONE Pay invoice".to_string(),
+            "CLOSED: [2026-07-20 Mon 09:00]".to_string(),
+        ];
+        let h = parse_single_org_entry(entry, &TodoKeywords::default()).unwrap();
+
+        assert_eq!(h.title, "Pay invoice");
+        assert_eq!(h.state, "DONE");
+        assert_eq!(
+            h.closed.unwrap().start,
+            NaiveDate::from_ymd(2026, 7, 20).and_hms(9, 0, 0)
+        );
     }
-    for e in event {
-        e.print_event();
+
+    #[test]
+    fn classify_finds_an_overdue_repeater_when_from_is_passed_as_now() {
+        let mut h = blank_heading("Water plants");
+        h.state = "TODO".to_string();
+        h.scheduled = Some(TimeRange {
+            start: NaiveDate::from_ymd(2026, 7, 20).and_hms(9, 0, 0),
+            end: NaiveDate::from_ymd(2026, 7, 20).and_hms(10, 0, 0),
+            repeater: parse_repeater("+1w"),
+        });
+
+        // `--from` doubles as the reference "now" for classify(). At this
+        // instant the 2026-07-20 occurrence has ended and the next one
+        // (2026-07-27) has not started, so is_overdue_now (which this
+        // exercises via classify) finds the heading overdue.
+        let now = NaiveDate::from_ymd(2026, 7, 26).and_hms(7, 44, 0);
+        let headings = [h];
+        let classified = classify(&headings, now);
+        assert_eq!(classified.overdue.len(), 1);
+        assert_eq!(classified.overdue[0].title, "Water plants");
     }
 }